@@ -3,12 +3,20 @@ use serde::{Deserialize, Serialize};
 pub const SETTING_API_KEY: &str = "api_key";
 pub const SETTING_API_BASE_URL: &str = "api_base_url";
 pub const SETTING_DEBUG_LOGS: &str = "debug_logs";
+pub const SETTING_BINARY_PATH: &str = "binary_path";
+pub const SETTING_BINARY_VERSION: &str = "binary_version";
+pub const SETTING_HEARTBEAT_INTERVAL_SECS: &str = "heartbeat_interval_secs";
+pub const SETTING_IDLE_TIMEOUT_SECS: &str = "idle_timeout_secs";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TestaustimeSettings {
     pub api_key: Option<String>,
     pub api_base_url: Option<String>,
     pub debug_logs: Option<bool>,
+    pub binary_path: Option<String>,
+    pub binary_version: Option<String>,
+    pub heartbeat_interval_secs: Option<u64>,
+    pub idle_timeout_secs: Option<u64>,
 }
 
 impl TestaustimeSettings {
@@ -23,6 +31,20 @@ impl TestaustimeSettings {
                 .and_then(|v| v.as_str())
                 .map(String::from),
             debug_logs: value.get(SETTING_DEBUG_LOGS).and_then(|v| v.as_bool()),
+            binary_path: value
+                .get(SETTING_BINARY_PATH)
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            binary_version: value
+                .get(SETTING_BINARY_VERSION)
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            heartbeat_interval_secs: value
+                .get(SETTING_HEARTBEAT_INTERVAL_SECS)
+                .and_then(|v| v.as_u64()),
+            idle_timeout_secs: value
+                .get(SETTING_IDLE_TIMEOUT_SECS)
+                .and_then(|v| v.as_u64()),
         }
     }
 
@@ -47,6 +69,34 @@ impl TestaustimeSettings {
             );
         }
 
+        if let Some(ref binary_path) = self.binary_path {
+            map.insert(
+                SETTING_BINARY_PATH.to_string(),
+                serde_json::json!(binary_path),
+            );
+        }
+
+        if let Some(ref binary_version) = self.binary_version {
+            map.insert(
+                SETTING_BINARY_VERSION.to_string(),
+                serde_json::json!(binary_version),
+            );
+        }
+
+        if let Some(heartbeat_interval_secs) = self.heartbeat_interval_secs {
+            map.insert(
+                SETTING_HEARTBEAT_INTERVAL_SECS.to_string(),
+                serde_json::json!(heartbeat_interval_secs),
+            );
+        }
+
+        if let Some(idle_timeout_secs) = self.idle_timeout_secs {
+            map.insert(
+                SETTING_IDLE_TIMEOUT_SECS.to_string(),
+                serde_json::json!(idle_timeout_secs),
+            );
+        }
+
         serde_json::Value::Object(map)
     }
 }
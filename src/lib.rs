@@ -10,15 +10,28 @@ struct TestaustimeExtension {
     cached_binary_path: Option<PathBuf>,
 }
 
-fn executable_name(binary: &str) -> String {
-    match zed::current_platform() {
-        (zed::Os::Windows, _) => format!("{binary}.exe"),
+fn executable_name(binary: &str, os: &str) -> String {
+    match os {
+        "pc-windows-msvc" => format!("{binary}.exe"),
         _ => binary.to_string(),
     }
 }
 
+fn lsp_settings(worktree: &Worktree) -> TestaustimeSettings {
+    let settings_json = zed::settings::LspSettings::for_worktree("testaustime", worktree)
+        .ok()
+        .and_then(|s| s.settings)
+        .unwrap_or_default();
+
+    TestaustimeSettings::from_json(&settings_json)
+}
+
 impl TestaustimeExtension {
-    fn target_triple(&self) -> Result<String> {
+    /// For an SSH project, Zed runs the extension itself on the remote host (that's what lets
+    /// `Worktree::which` and friends see the remote filesystem), so `zed::current_platform()`
+    /// already reports the host the language server needs to run on, remote or local, with no
+    /// extra detection required.
+    fn target_triple(&self) -> Result<(String, &'static str)> {
         let (platform, arch) = zed::current_platform();
 
         let arch = match arch {
@@ -33,29 +46,45 @@ impl TestaustimeExtension {
             zed::Os::Windows => "pc-windows-msvc",
         };
 
-        Ok(format!("testaustime-ls-{arch}-{os}"))
+        Ok((format!("testaustime-ls-{arch}-{os}"), os))
     }
 
-    fn download(&self, language_server_id: &LanguageServerId) -> Result<PathBuf> {
-        let release = zed::latest_github_release(
-            "testaustime/testaustime-zed",
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        )?;
-
-        let target_triple = self.target_triple()?;
+    fn download(
+        &self,
+        language_server_id: &LanguageServerId,
+        binary_version: Option<&str>,
+    ) -> Result<PathBuf> {
+        let (target_triple, os) = self.target_triple()?;
         let asset_name = format!("{target_triple}.zip");
 
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {asset_name:?}"))?;
+        let (version, download_url) = match binary_version {
+            Some(pinned) => {
+                let download_url = format!(
+                    "https://github.com/testaustime/testaustime-zed/releases/download/{pinned}/{asset_name}"
+                );
+                (pinned.to_string(), download_url)
+            }
+            None => {
+                let release = zed::latest_github_release(
+                    "testaustime/testaustime-zed",
+                    zed::GithubReleaseOptions {
+                        require_assets: true,
+                        pre_release: false,
+                    },
+                )?;
+
+                let asset = release
+                    .assets
+                    .iter()
+                    .find(|asset| asset.name == asset_name)
+                    .ok_or_else(|| format!("no asset found matching {asset_name:?}"))?;
+
+                (release.version.clone(), asset.download_url.clone())
+            }
+        };
 
-        let version_dir = format!("testaustime-ls-{}", release.version);
-        let binary_path = Path::new(&version_dir).join(executable_name("testaustime-ls"));
+        let version_dir = format!("testaustime-ls-{version}");
+        let binary_path = Path::new(&version_dir).join(executable_name("testaustime-ls", os));
 
         if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
             zed::set_language_server_installation_status(
@@ -63,12 +92,9 @@ impl TestaustimeExtension {
                 &zed::LanguageServerInstallationStatus::Downloading,
             );
 
-            zed::download_file(
-                &asset.download_url,
-                &version_dir,
-                zed::DownloadedFileType::Zip,
-            )
-            .map_err(|err| format!("failed to download file: {err}"))?;
+            zed::download_file(&download_url, &version_dir, zed::DownloadedFileType::Zip).map_err(
+                |err| format!("failed to download testaustime-ls {version} ({asset_name}): {err}"),
+            )?;
 
             // remove old versions
             let entries = fs::read_dir(".")
@@ -95,13 +121,24 @@ impl TestaustimeExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<PathBuf> {
+        let settings = lsp_settings(worktree);
+
+        // user-provided binary, bypass $PATH/cache/download entirely
+        if let Some(binary_path) = settings.binary_path {
+            return Ok(PathBuf::from(binary_path));
+        }
+
         zed::set_language_server_installation_status(
             language_server_id,
             &zed::LanguageServerInstallationStatus::CheckingForUpdate,
         );
 
-        // check $PATH
-        if let Some(path) = worktree.which(&executable_name("testaustime-ls")) {
+        // check $PATH; try both names since we don't know the worktree's OS until we need to
+        // pick a download asset, and an already-installed binary doesn't require knowing it
+        if let Some(path) = worktree
+            .which("testaustime-ls")
+            .or_else(|| worktree.which("testaustime-ls.exe"))
+        {
             return Ok(path.into());
         }
 
@@ -113,7 +150,7 @@ impl TestaustimeExtension {
         }
 
         // download
-        let binary_path = self.download(language_server_id)?;
+        let binary_path = self.download(language_server_id, settings.binary_version.as_deref())?;
         self.cached_binary_path = Some(binary_path.clone());
 
         Ok(binary_path)
@@ -146,13 +183,15 @@ impl zed::Extension for TestaustimeExtension {
         _language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        let settings_json = zed::settings::LspSettings::for_worktree("testaustime", worktree)
-            .ok()
-            .and_then(|s| s.settings)
-            .unwrap_or_default();
+        Ok(Some(lsp_settings(worktree).to_init_options()))
+    }
 
-        let settings = TestaustimeSettings::from_json(&settings_json);
-        Ok(Some(settings.to_init_options()))
+    fn language_server_workspace_configuration(
+        &mut self,
+        _language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        Ok(Some(lsp_settings(worktree).to_init_options()))
     }
 }
 
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
@@ -8,23 +9,42 @@ pub struct APIClient {
     api_key: String,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ActivityUpdate {
     project_name: String,
     language: String,
     editor_name: String,
     hostname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<DateTime<Local>>,
 }
 
 impl ActivityUpdate {
-    pub fn new(project_name: String, language: String, hostname: String) -> Self {
+    pub fn new(
+        project_name: String,
+        language: String,
+        hostname: String,
+        branch: Option<String>,
+    ) -> Self {
         Self {
             project_name,
             language,
             editor_name: "Zed".to_string(),
             hostname,
+            branch,
+            timestamp: None,
         }
     }
+
+    /// Stamp this activity with the time it originally happened, used when replaying a
+    /// heartbeat that had been spooled to disk after a failed send so the backend doesn't
+    /// record offline work as happening at flush time.
+    pub fn with_timestamp(mut self, timestamp: DateTime<Local>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
 }
 
 #[derive(Deserialize)]
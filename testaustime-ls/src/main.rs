@@ -1,4 +1,8 @@
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use arc_swap::ArcSwap;
 use chrono::{DateTime, Local, TimeDelta};
@@ -7,7 +11,66 @@ use tokio::sync::Mutex;
 use tower_lsp::{Client, LanguageServer, LspService, Server, jsonrpc::Result, lsp_types::*};
 
 mod api;
+mod spool;
 use api::{APIClient, ActivityUpdate};
+use spool::Spool;
+
+const SPOOL_RETRY_MIN_BACKOFF: Duration = Duration::from_secs(5);
+const SPOOL_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(300);
+const SPOOL_IDLE_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+fn data_dir() -> PathBuf {
+    directories::ProjectDirs::from("fi", "testaustime", "testaustime-ls")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Read the current Git branch for `workspace_root`, following `.git/HEAD`'s symref, or falling
+/// back to a detached short SHA.
+async fn read_git_branch(workspace_root: &Path) -> Option<String> {
+    let head = tokio::fs::read_to_string(workspace_root.join(".git/HEAD"))
+        .await
+        .ok()?;
+    let head = head.trim();
+
+    if let Some(branch) = head.strip_prefix("ref: refs/heads/") {
+        Some(branch.to_string())
+    } else {
+        Some(head.chars().take(7).collect())
+    }
+}
+
+/// Periodically retries spooled heartbeats with exponential backoff, so activity recorded
+/// while offline still reaches the backend once connectivity comes back.
+async fn spool_retry_loop(server: Arc<TestaustimeLanguageServer>) {
+    let mut backoff = SPOOL_RETRY_MIN_BACKOFF;
+
+    loop {
+        if server.spool.is_empty().await {
+            tokio::time::sleep(SPOOL_IDLE_POLL_INTERVAL).await;
+            backoff = SPOOL_RETRY_MIN_BACKOFF;
+            continue;
+        }
+
+        tokio::time::sleep(backoff).await;
+
+        let api_client = server.api_client.lock().await;
+        let Some(ref client) = *api_client else {
+            // no client configured yet: back off like a failed drain instead of spinning at
+            // the minimum backoff forever
+            backoff = (backoff * 2).min(SPOOL_RETRY_MAX_BACKOFF);
+            continue;
+        };
+
+        server.spool.drain(client).await;
+
+        backoff = if server.spool.is_empty().await {
+            SPOOL_RETRY_MIN_BACKOFF
+        } else {
+            (backoff * 2).min(SPOOL_RETRY_MAX_BACKOFF)
+        };
+    }
+}
 
 macro_rules! debug_log {
     ($self:expr, $($arg:tt)*) => {
@@ -34,17 +97,51 @@ struct TestaustimeLanguageServer {
     api_client: Mutex<Option<APIClient>>,
     last_heartbeat: Mutex<DateTime<Local>>,
     workspace_name: ArcSwap<Option<String>>,
+    workspace_root: ArcSwap<Option<PathBuf>>,
     last_language: ArcSwap<String>,
+    git_branch: ArcSwap<Option<String>>,
+    last_event: Mutex<DateTime<Local>>,
+    spool: Spool,
 }
 
 impl TestaustimeLanguageServer {
     async fn send(&self, event: Event) {
-        const INTERVAL: TimeDelta = TimeDelta::seconds(30);
+        const DEFAULT_HEARTBEAT_INTERVAL_SECS: i64 = 30;
+        const DEFAULT_IDLE_TIMEOUT_SECS: i64 = 300;
+
+        let settings = self.settings.load();
+        let interval = TimeDelta::seconds(
+            settings
+                .heartbeat_interval_secs
+                .map(|secs| secs as i64)
+                .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_SECS),
+        );
+        let idle_timeout = TimeDelta::seconds(
+            settings
+                .idle_timeout_secs
+                .map(|secs| secs as i64)
+                .unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS),
+        );
 
-        let mut last_heartbeat = self.last_heartbeat.lock().await;
         let now = Local::now();
+        let mut last_event = self.last_event.lock().await;
+        let idle_for = now - *last_event;
+        *last_event = now;
+
+        if idle_for > idle_timeout {
+            // resuming after being idle: start a fresh interval instead of back-filling the gap
+            *self.last_heartbeat.lock().await = now;
+            debug_log!(
+                self,
+                "Idle for {}s, starting a fresh interval",
+                idle_for.num_seconds()
+            );
+            return;
+        }
+
+        let mut last_heartbeat = self.last_heartbeat.lock().await;
 
-        if now - *last_heartbeat < INTERVAL && !event.is_write {
+        if now - *last_heartbeat < interval && !event.is_write {
             return;
         }
 
@@ -83,20 +180,28 @@ impl TestaustimeLanguageServer {
                 .ok()
                 .and_then(|h| h.into_string().ok())
                 .unwrap_or_else(|| "unknown".to_string()),
+            self.git_branch.load().as_ref().clone(),
         );
 
         debug_log!(self, "Heartbeat data: {:?}", activity);
 
-        match client.heartbeat(activity).await {
+        match client.heartbeat(activity.clone()).await {
             Ok(_) => {
                 self.client
                     .log_message(MessageType::LOG, "Heartbeat sent successfully")
                     .await;
+
+                self.spool.drain(client).await;
             }
             Err(e) => {
                 self.client
                     .log_message(MessageType::ERROR, format!("Heartbeat failed: {}", e))
                     .await;
+
+                self.spool.push(activity).await;
+                self.client
+                    .log_message(MessageType::WARNING, "Heartbeat queued for retry")
+                    .await;
             }
         }
     }
@@ -127,9 +232,21 @@ impl LanguageServer for TestaustimeLanguageServer {
                     }
                 });
 
+            let workspace_root = params
+                .workspace_folders
+                .as_ref()
+                .and_then(|folders| folders.first())
+                .and_then(|folder| folder.uri.to_file_path().ok());
+
             debug_log!(self, "Workspace folders: {:?}", params.workspace_folders);
 
             self.workspace_name.swap(Arc::new(workspace_name));
+            self.workspace_root.swap(Arc::new(workspace_root.clone()));
+
+            if let Some(ref workspace_root) = workspace_root {
+                self.git_branch
+                    .swap(Arc::new(read_git_branch(workspace_root).await));
+            }
 
             if let Some(ref api_key) = settings.api_key {
                 let client = APIClient::new(api_key.clone(), settings.api_base_url.clone());
@@ -173,14 +290,59 @@ impl LanguageServer for TestaustimeLanguageServer {
     }
 
     async fn initialized(&self, _params: InitializedParams) {
+        self.spool.load().await;
+
         self.client
             .log_message(MessageType::INFO, "Testaustime language server initialized")
             .await;
     }
 
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        let settings = TestaustimeSettings::from_json(&params.settings);
+
+        debug_log!(self, "Configuration changed: {:?}", settings);
+
+        let previous = self.settings.load();
+        let key_or_base_url_changed =
+            previous.api_key != settings.api_key || previous.api_base_url != settings.api_base_url;
+
+        if key_or_base_url_changed {
+            let mut api_client = self.api_client.lock().await;
+
+            if let Some(ref api_key) = settings.api_key {
+                let client = APIClient::new(api_key.clone(), settings.api_base_url.clone());
+                match client.validate_api_key(api_key).await {
+                    Ok(me) => {
+                        self.client
+                            .log_message(
+                                MessageType::INFO,
+                                format!("Testaustime authenticated as: {}", me.username),
+                            )
+                            .await;
+                        *api_client = Some(client);
+                    }
+                    Err(e) => {
+                        self.client
+                            .log_message(MessageType::ERROR, format!("Invalid API key: {}", e))
+                            .await;
+                        *api_client = None;
+                    }
+                }
+            } else {
+                self.client
+                    .log_message(MessageType::WARNING, "No API key provided")
+                    .await;
+                *api_client = None;
+            }
+        }
+
+        self.settings.swap(Arc::new(settings));
+    }
+
     async fn shutdown(&self) -> Result<()> {
         let api_client = self.api_client.lock().await;
         if let Some(ref client) = *api_client {
+            self.spool.drain(client).await;
             let _ = client.flush().await;
         } else {
             self.client
@@ -212,6 +374,11 @@ impl LanguageServer for TestaustimeLanguageServer {
     }
 
     async fn did_save(&self, _params: DidSaveTextDocumentParams) {
+        if let Some(workspace_root) = self.workspace_root.load().as_ref().clone() {
+            self.git_branch
+                .swap(Arc::new(read_git_branch(&workspace_root).await));
+        }
+
         let event = Event {
             is_write: true,
             language: None,
@@ -227,14 +394,22 @@ async fn main() {
     let stdout = tokio::io::stdout();
 
     let (service, socket) = LspService::new(|client| {
-        Arc::new(TestaustimeLanguageServer {
+        let server = Arc::new(TestaustimeLanguageServer {
             client,
             settings: ArcSwap::from_pointee(TestaustimeSettings::default()),
             last_heartbeat: Mutex::new(Local::now() - TimeDelta::seconds(31)),
             api_client: Mutex::new(None),
             workspace_name: ArcSwap::from_pointee(None),
+            workspace_root: ArcSwap::from_pointee(None),
             last_language: ArcSwap::from_pointee("Unknown".to_string()),
-        })
+            git_branch: ArcSwap::from_pointee(None),
+            last_event: Mutex::new(Local::now()),
+            spool: Spool::new(&data_dir()),
+        });
+
+        tokio::spawn(spool_retry_loop(server.clone()));
+
+        server
     });
     Server::new(stdin, stdout, socket).serve(service).await;
 }
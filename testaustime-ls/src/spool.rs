@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, sync::Mutex};
+
+use crate::api::{APIClient, ActivityUpdate};
+
+const SPOOL_FILE_NAME: &str = "heartbeat-spool.json";
+/// Caps how many heartbeats an extended outage can queue up; the oldest are dropped first since
+/// they're the least useful to report once there are too many to catch up on.
+const MAX_QUEUED_ACTIVITIES: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct QueuedActivity {
+    activity: ActivityUpdate,
+    queued_at: DateTime<Local>,
+}
+
+/// Durable on-disk queue of heartbeats that failed to send, so they survive a crash or restart
+/// instead of being lost while offline.
+pub struct Spool {
+    path: PathBuf,
+    queue: Mutex<Vec<QueuedActivity>>,
+}
+
+impl Spool {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(SPOOL_FILE_NAME),
+            queue: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load any activities spooled by a previous run of the server.
+    pub async fn load(&self) {
+        let Ok(contents) = fs::read_to_string(&self.path).await else {
+            return;
+        };
+
+        if let Ok(queued) = serde_json::from_str(&contents) {
+            *self.queue.lock().await = queued;
+        }
+    }
+
+    pub async fn push(&self, activity: ActivityUpdate) {
+        let mut queue = self.queue.lock().await;
+        queue.push(QueuedActivity {
+            activity,
+            queued_at: Local::now(),
+        });
+
+        let overflow = queue.len().saturating_sub(MAX_QUEUED_ACTIVITIES);
+        if overflow > 0 {
+            queue.drain(..overflow);
+        }
+
+        self.persist(&queue).await;
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.queue.lock().await.is_empty()
+    }
+
+    /// Resend every queued activity in order, stopping at (and keeping) the first one that
+    /// still fails so later retries don't reorder activities in front of it.
+    pub async fn drain(&self, client: &APIClient) {
+        let mut queue = self.queue.lock().await;
+        if queue.is_empty() {
+            return;
+        }
+
+        let mut remaining = queue.drain(..).collect::<Vec<_>>().into_iter();
+        let mut failed = Vec::new();
+
+        for queued in remaining.by_ref() {
+            let activity = queued.activity.clone().with_timestamp(queued.queued_at);
+            if client.heartbeat(activity).await.is_err() {
+                failed.push(queued);
+                break;
+            }
+        }
+
+        failed.extend(remaining);
+        *queue = failed;
+
+        self.persist(&queue).await;
+    }
+
+    async fn persist(&self, queue: &[QueuedActivity]) {
+        let Ok(json) = serde_json::to_string(queue) else {
+            return;
+        };
+
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent).await;
+        }
+
+        let _ = fs::write(&self.path, json).await;
+    }
+}